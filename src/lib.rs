@@ -1,5 +1,5 @@
-use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
-use serde::Serialize;
+use aho_corasick::{packed, AhoCorasick, AhoCorasickBuilder, AhoCorasickKind, MatchKind};
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
 
@@ -13,44 +13,517 @@ pub struct MatchResult {
     pattern_index: usize,
     start: usize,
     end: usize,
+    // UTF-16 code-unit offsets, valid for indexing a JS string directly (e.g.
+    // `str.slice(start_utf16, end_utf16)`); `start`/`end` above remain UTF-8 byte offsets.
+    start_utf16: usize,
+    end_utf16: usize,
 }
 
+/// Maps each UTF-8 byte offset in `haystack` (only char-boundary offsets are ever
+/// looked up) to the corresponding cumulative UTF-16 code-unit offset.
+fn build_utf16_offsets(haystack: &str) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(haystack.len() + 1);
+    offsets.push(0);
+    let mut utf16_len = 0usize;
+    for ch in haystack.chars() {
+        utf16_len += ch.len_utf16();
+        for _ in 0..ch.len_utf8() {
+            offsets.push(utf16_len);
+        }
+    }
+    offsets
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[cfg_attr(test, derive(Serialize))]
+pub struct AhoSearcherConfig {
+    #[serde(default)]
+    match_kind: Option<String>,
+    #[serde(default)]
+    ascii_case_insensitive: bool,
+    #[serde(default)]
+    kind: Option<String>,
+    #[serde(default)]
+    max_memory_bytes: Option<usize>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AutomatonStats {
+    memory_usage: usize,
+    pattern_count: usize,
+    min_pattern_len: usize,
+    max_pattern_len: usize,
+    // Which search engine is actually backing `search()`: "packed" for the SIMD
+    // multi-substring prefilter, or the selected automaton kind otherwise.
+    engine: &'static str,
+}
+
+fn automaton_kind_label(ac: &AhoCorasick) -> &'static str {
+    match ac.kind() {
+        AhoCorasickKind::NoncontiguousNFA => "noncontiguous-nfa",
+        AhoCorasickKind::ContiguousNFA => "contiguous-nfa",
+        AhoCorasickKind::DFA => "dfa",
+        _ => "auto",
+    }
+}
+
+fn parse_match_kind(value: &str) -> Result<MatchKind, JsValue> {
+    match value {
+        "standard" => Ok(MatchKind::Standard),
+        "leftmost-first" => Ok(MatchKind::LeftmostFirst),
+        "leftmost-longest" => Ok(MatchKind::LeftmostLongest),
+        other => Err(JsValue::from_str(&format!(
+            "不支持的 match_kind：{}（可选值：standard, leftmost-first, leftmost-longest）",
+            other
+        ))),
+    }
+}
+
+fn parse_kind(value: &str) -> Result<AhoCorasickKind, JsValue> {
+    match value {
+        "noncontiguous-nfa" => Ok(AhoCorasickKind::NoncontiguousNFA),
+        "contiguous-nfa" => Ok(AhoCorasickKind::ContiguousNFA),
+        "dfa" => Ok(AhoCorasickKind::DFA),
+        other => Err(JsValue::from_str(&format!(
+            "不支持的 kind：{}（可选值：noncontiguous-nfa, contiguous-nfa, dfa）",
+            other
+        ))),
+    }
+}
+
+fn build_packed(patterns: &[String], match_kind: MatchKind) -> Result<Option<packed::Searcher>, JsValue> {
+    let packed_kind = match match_kind {
+        MatchKind::LeftmostFirst => packed::MatchKind::LeftmostFirst,
+        MatchKind::LeftmostLongest => packed::MatchKind::LeftmostLongest,
+        _ => {
+            return Err(JsValue::from_str(
+                "kind 为 packed 时不支持 match_kind：standard（packed 搜索器仅支持 leftmost-first 或 leftmost-longest）",
+            ))
+        }
+    };
+    Ok(packed::Config::new()
+        .match_kind(packed_kind)
+        .builder()
+        .extend(patterns)
+        .build())
+}
+
+fn build_automaton(
+    patterns: &[String],
+    config: &AhoSearcherConfig,
+) -> Result<(AhoCorasick, Option<packed::Searcher>), JsValue> {
+    let mut builder = AhoCorasickBuilder::new();
+
+    let match_kind = match &config.match_kind {
+        Some(value) => parse_match_kind(value)?,
+        None => MatchKind::Standard,
+    };
+    builder.match_kind(match_kind);
+    builder.ascii_case_insensitive(config.ascii_case_insensitive);
+
+    // "packed" isn't an `AhoCorasickKind`: it makes `search()` use the SIMD packed
+    // multi-substring searcher in place of the automaton. We still build the automaton
+    // below as a fallback for pattern sets the packed searcher doesn't support (and to
+    // power the other methods, which only know how to search the automaton).
+    let packed = match &config.kind {
+        Some(value) if value == "packed" => {
+            // `match_kind: "standard"` is never usable with packed, independent of
+            // case-insensitivity, so reject it up front rather than letting it fall
+            // through to a silent NFA substitution below.
+            if match_kind == MatchKind::Standard {
+                return Err(JsValue::from_str(
+                    "kind 为 packed 时不支持 match_kind：standard（packed 搜索器仅支持 leftmost-first 或 leftmost-longest）",
+                ));
+            }
+            if config.ascii_case_insensitive {
+                // The packed searcher has no case-insensitivity knob; fall back to
+                // the automaton below, which does, rather than silently ignoring it.
+                None
+            } else {
+                build_packed(patterns, match_kind)?
+            }
+        }
+        Some(value) => {
+            builder.kind(Some(parse_kind(value)?));
+            None
+        }
+        None => None,
+    };
+
+    let ac = builder
+        .build(patterns)
+        .map_err(|e| JsValue::from_str(&format!("构建 AhoCorasick 失败：{}", e)))?;
+
+    if let Some(budget) = config.max_memory_bytes {
+        let used = ac.memory_usage();
+        if used > budget {
+            return Err(JsValue::from_str(&format!(
+                "构建 AhoCorasick 失败：自动机占用内存 {} 字节，超出了设定的 max_memory_bytes（{} 字节），请考虑使用 noncontiguous-nfa 等更省内存的 kind 或精简 pattern 集合",
+                used, budget
+            )));
+        }
+    }
+
+    Ok((ac, packed))
+}
 
 #[wasm_bindgen]
 pub struct AhoSearcher {
     ac: AhoCorasick,
+    packed: Option<packed::Searcher>,
 }
 
 #[wasm_bindgen]
 impl AhoSearcher {
-    
+
     #[wasm_bindgen(constructor)]
-    pub fn new(patterns: JsValue) -> Result<AhoSearcher, JsValue> {
-        
+    pub fn new(patterns: JsValue, config: JsValue) -> Result<AhoSearcher, JsValue> {
+
         let patterns_vec: Vec<String> = serde_wasm_bindgen::from_value(patterns)
             .map_err(|e| JsValue::from_str(&format!("初始化失败：需要一个字符串数组。 {}", e)))?;
 
-        
-        let ac = AhoCorasickBuilder::new()
-            .match_kind(MatchKind::Standard)
-            .build(&patterns_vec)
-            .map_err(|e| JsValue::from_str(&format!("构建 AhoCorasick 失败：{}", e)))?;
+        let config: AhoSearcherConfig = if config.is_undefined() || config.is_null() {
+            AhoSearcherConfig::default()
+        } else {
+            serde_wasm_bindgen::from_value(config)
+                .map_err(|e| JsValue::from_str(&format!("初始化失败：配置格式不正确。 {}", e)))?
+        };
 
-        
-        Ok(AhoSearcher { ac })
+        let (ac, packed) = build_automaton(&patterns_vec, &config)?;
+
+        Ok(AhoSearcher { ac, packed })
     }
-    
+
     pub fn search(&self, haystack: &str) -> Result<JsValue, JsValue> {
-        
+
+        let utf16_offsets = build_utf16_offsets(haystack);
+        let mut matches = Vec::new();
+
+        if let Some(packed) = &self.packed {
+            for mat in packed.find_iter(haystack.as_bytes()) {
+                matches.push(MatchResult {
+                    pattern_index: mat.pattern().as_usize(),
+                    start: mat.start(),
+                    end: mat.end(),
+                    start_utf16: utf16_offsets[mat.start()],
+                    end_utf16: utf16_offsets[mat.end()],
+                });
+            }
+        } else {
+            for mat in self.ac.find_iter(haystack) {
+                matches.push(MatchResult {
+                    pattern_index: mat.pattern().as_usize(),
+                    start: mat.start(),
+                    end: mat.end(),
+                    start_utf16: utf16_offsets[mat.start()],
+                    end_utf16: utf16_offsets[mat.end()],
+                });
+            }
+        }
+        serde_wasm_bindgen::to_value(&matches)
+            .map_err(|e| JsValue::from_str(&format!("序列化匹配结果失败：{}", e)))
+    }
+
+    pub fn search_overlapping(&self, haystack: &str) -> Result<JsValue, JsValue> {
+
+        if self.ac.match_kind() != MatchKind::Standard {
+            return Err(JsValue::from_str(
+                "重叠匹配（search_overlapping）仅支持 match_kind 为 standard 的自动机",
+            ));
+        }
+
+        let utf16_offsets = build_utf16_offsets(haystack);
         let mut matches = Vec::new();
-        for mat in self.ac.find_iter(haystack) {
+        for mat in self.ac.find_overlapping_iter(haystack) {
             matches.push(MatchResult {
                 pattern_index: mat.pattern().as_usize(),
                 start: mat.start(),
                 end: mat.end(),
+                start_utf16: utf16_offsets[mat.start()],
+                end_utf16: utf16_offsets[mat.end()],
             });
         }
         serde_wasm_bindgen::to_value(&matches)
             .map_err(|e| JsValue::from_str(&format!("序列化匹配结果失败：{}", e)))
     }
-}
\ No newline at end of file
+
+    pub fn replace_all(&self, haystack: &str, replacements: JsValue) -> Result<String, JsValue> {
+
+        let replacements_vec: Vec<String> = serde_wasm_bindgen::from_value(replacements)
+            .map_err(|e| JsValue::from_str(&format!("replace_all 参数错误：需要一个字符串数组。 {}", e)))?;
+
+        if replacements_vec.len() != self.ac.patterns_len() {
+            return Err(JsValue::from_str(&format!(
+                "replace_all 参数错误：replacements 长度（{}）与 pattern 数量（{}）不一致",
+                replacements_vec.len(),
+                self.ac.patterns_len()
+            )));
+        }
+
+        Ok(self.ac.replace_all(haystack, &replacements_vec))
+    }
+
+    pub fn stats(&self) -> Result<JsValue, JsValue> {
+
+        let stats = AutomatonStats {
+            memory_usage: self.ac.memory_usage(),
+            pattern_count: self.ac.patterns_len(),
+            min_pattern_len: self.ac.min_pattern_len(),
+            max_pattern_len: self.ac.max_pattern_len(),
+            engine: if self.packed.is_some() {
+                "packed"
+            } else {
+                automaton_kind_label(&self.ac)
+            },
+        };
+        serde_wasm_bindgen::to_value(&stats)
+            .map_err(|e| JsValue::from_str(&format!("序列化统计信息失败：{}", e)))
+    }
+}
+
+/// Stateful searcher for feeding a haystack across the WASM boundary in chunks
+/// (e.g. from a JS `ReadableStream`) instead of copying the whole input at once.
+///
+/// Only `match_kind: "standard"` automatons are supported. `leftmost-first` and
+/// `leftmost-longest` both require, in general, unbounded lookahead past a
+/// candidate match to decide whether a higher-priority or longer alternative is
+/// still possible — a match "found" against one chunk could be invalidated by
+/// bytes that arrive in a later chunk. Standard semantics commit to the first
+/// completed pattern and never reconsider, which is what makes a match reported
+/// against `pending` (below) final and safe to hand to the caller immediately.
+#[wasm_bindgen]
+pub struct AhoStreamSearcher {
+    ac: AhoCorasick,
+    // The undecided tail of the stream: bytes after the last reported match, which
+    // `push` trims to the last `max_pattern_len - 1` bytes (widened back to a char
+    // boundary) once a chunk has been searched. The next search always restarts at
+    // position 0 of this buffer.
+    pending: String,
+    // Absolute stream offset of `pending`'s first byte.
+    pending_offset: usize,
+    pending_offset_utf16: usize,
+}
+
+#[wasm_bindgen]
+impl AhoStreamSearcher {
+
+    #[wasm_bindgen(constructor)]
+    pub fn new(patterns: JsValue, config: JsValue) -> Result<AhoStreamSearcher, JsValue> {
+
+        let patterns_vec: Vec<String> = serde_wasm_bindgen::from_value(patterns)
+            .map_err(|e| JsValue::from_str(&format!("初始化失败：需要一个字符串数组。 {}", e)))?;
+
+        let config: AhoSearcherConfig = if config.is_undefined() || config.is_null() {
+            AhoSearcherConfig::default()
+        } else {
+            serde_wasm_bindgen::from_value(config)
+                .map_err(|e| JsValue::from_str(&format!("初始化失败：配置格式不正确。 {}", e)))?
+        };
+
+        // The packed prefilter doesn't fit the resumable chunk-at-a-time model below,
+        // so streaming always searches through the full automaton.
+        let (ac, _packed) = build_automaton(&patterns_vec, &config)?;
+
+        if ac.match_kind() != MatchKind::Standard {
+            return Err(JsValue::from_str(
+                "AhoStreamSearcher 目前只支持 match_kind 为 standard：leftmost-first/leftmost-longest 的匹配结果可能随着后续数据块的到达而改变，无法在分片流式搜索中提前安全上报",
+            ));
+        }
+
+        Ok(AhoStreamSearcher {
+            ac,
+            pending: String::new(),
+            pending_offset: 0,
+            pending_offset_utf16: 0,
+        })
+    }
+
+    pub fn push(&mut self, chunk: &str) -> Result<JsValue, JsValue> {
+
+        self.pending.push_str(chunk);
+        let utf16_offsets = build_utf16_offsets(&self.pending);
+
+        let mut matches = Vec::new();
+        let mut consumed = 0;
+        let mut consumed_utf16 = 0;
+        for mat in self.ac.find_iter(&self.pending) {
+            matches.push(MatchResult {
+                pattern_index: mat.pattern().as_usize(),
+                start: self.pending_offset + mat.start(),
+                end: self.pending_offset + mat.end(),
+                start_utf16: self.pending_offset_utf16 + utf16_offsets[mat.start()],
+                end_utf16: self.pending_offset_utf16 + utf16_offsets[mat.end()],
+            });
+            consumed = mat.end();
+            consumed_utf16 = utf16_offsets[mat.end()];
+        }
+
+        // Everything up to the end of the last match is settled (standard match
+        // kind never reconsiders a completed match); drop it so `pending` only
+        // holds the as-yet-undecided suffix.
+        self.pending.drain(..consumed);
+        self.pending_offset += consumed;
+        self.pending_offset_utf16 += consumed_utf16;
+
+        // Of what's left, anything further than `max_pattern_len - 1` bytes from
+        // the end can never start a future match (no pattern is longer than that,
+        // and `find_iter` above already would have reported it otherwise), so it's
+        // safe to drop too. This bounds `pending` to roughly one chunk plus one
+        // pattern length instead of letting it grow with the whole stream.
+        let max_keep = self.ac.max_pattern_len().saturating_sub(1);
+        if self.pending.len() > max_keep {
+            let mut drop_to = self.pending.len() - max_keep;
+            while drop_to > 0 && !self.pending.is_char_boundary(drop_to) {
+                drop_to -= 1;
+            }
+            let dropped_utf16: usize = self.pending[..drop_to].chars().map(|c| c.len_utf16()).sum();
+            self.pending.drain(..drop_to);
+            self.pending_offset += drop_to;
+            self.pending_offset_utf16 += dropped_utf16;
+        }
+
+        serde_wasm_bindgen::to_value(&matches)
+            .map_err(|e| JsValue::from_str(&format!("序列化匹配结果失败：{}", e)))
+    }
+
+    pub fn finish(&mut self) {
+        self.pending.clear();
+    }
+}
+
+// These tests exercise real `JsValue`/`js_sys` objects (via `serde_wasm_bindgen`
+// and the `#[wasm_bindgen]` constructors themselves), which only work when
+// compiled to wasm32 with a JS host attached. Run with
+// `wasm-pack test --node` (or `--chrome`/`--firefox`), not plain `cargo test`.
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn patterns_js(patterns: &[&str]) -> JsValue {
+        serde_wasm_bindgen::to_value(&patterns.to_vec()).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn stream_searcher_resyncs_across_self_overlapping_chunk_boundary() {
+        // Regression test: pattern "aa" fed as "aaaa" then "aa" must report the
+        // same non-overlapping matches as a single continuous search over "aaaaaa".
+        let mut searcher =
+            AhoStreamSearcher::new(patterns_js(&["aa"]), JsValue::UNDEFINED).unwrap();
+
+        let mut got: Vec<(usize, usize)> = Vec::new();
+        for chunk in ["aaaa", "aa"] {
+            let result = searcher.push(chunk).unwrap();
+            let matches: Vec<MatchResultForTest> =
+                serde_wasm_bindgen::from_value(result).unwrap();
+            got.extend(matches.into_iter().map(|m| (m.start, m.end)));
+        }
+
+        let ac = AhoCorasick::new(["aa"]).unwrap();
+        let want: Vec<(usize, usize)> = ac
+            .find_iter("aaaaaa")
+            .map(|m| (m.start(), m.end()))
+            .collect();
+
+        assert_eq!(got, want);
+    }
+
+    #[wasm_bindgen_test]
+    fn stream_searcher_rejects_non_standard_match_kind() {
+        let mut cfg = std::collections::HashMap::new();
+        cfg.insert("match_kind", "leftmost-first");
+        let config = serde_wasm_bindgen::to_value(&cfg).unwrap();
+        assert!(AhoStreamSearcher::new(patterns_js(&["aa"]), config).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn stats_reports_pattern_and_length_info() {
+        let searcher =
+            AhoSearcher::new(patterns_js(&["a", "abc"]), JsValue::UNDEFINED).unwrap();
+        let stats: AutomatonStatsForTest =
+            serde_wasm_bindgen::from_value(searcher.stats().unwrap()).unwrap();
+
+        assert_eq!(stats.pattern_count, 2);
+        assert_eq!(stats.min_pattern_len, 1);
+        assert_eq!(stats.max_pattern_len, 3);
+        assert!(stats.memory_usage > 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn max_memory_bytes_rejects_oversized_automaton() {
+        let mut cfg = std::collections::HashMap::new();
+        cfg.insert("max_memory_bytes", 1usize);
+        let config = serde_wasm_bindgen::to_value(&cfg).unwrap();
+        assert!(AhoSearcher::new(patterns_js(&["a", "abc", "xyz"]), config).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn packed_kind_is_selected_and_used_for_search() {
+        let mut cfg = std::collections::HashMap::new();
+        cfg.insert("kind", "packed");
+        cfg.insert("match_kind", "leftmost-first");
+        let config = serde_wasm_bindgen::to_value(&cfg).unwrap();
+
+        let searcher = AhoSearcher::new(patterns_js(&["foo", "bar"]), config).unwrap();
+        let stats: AutomatonStatsForTest =
+            serde_wasm_bindgen::from_value(searcher.stats().unwrap()).unwrap();
+        assert_eq!(stats.engine, "packed");
+
+        let matches: Vec<MatchResultForTest> =
+            serde_wasm_bindgen::from_value(searcher.search("xx foo yy bar").unwrap()).unwrap();
+        assert_eq!(
+            matches.into_iter().map(|m| (m.start, m.end)).collect::<Vec<_>>(),
+            vec![(3, 6), (10, 13)]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn packed_kind_rejects_standard_match_kind() {
+        let mut cfg = std::collections::HashMap::new();
+        cfg.insert("kind", "packed");
+        let config = serde_wasm_bindgen::to_value(&cfg).unwrap();
+        // `match_kind` defaults to "standard", which the packed searcher can't express.
+        assert!(AhoSearcher::new(patterns_js(&["foo", "bar"]), config).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn packed_kind_falls_back_to_automaton_when_ascii_case_insensitive() {
+        let config = AhoSearcherConfig {
+            kind: Some("packed".to_string()),
+            match_kind: Some("leftmost-first".to_string()),
+            ascii_case_insensitive: true,
+            max_memory_bytes: None,
+        };
+        let config = serde_wasm_bindgen::to_value(&config).unwrap();
+
+        let searcher = AhoSearcher::new(patterns_js(&["foo"]), config).unwrap();
+        let stats: AutomatonStatsForTest =
+            serde_wasm_bindgen::from_value(searcher.stats().unwrap()).unwrap();
+        assert_ne!(stats.engine, "packed");
+    }
+
+    // Mirrors `AutomatonStats`'s wire shape for deserializing `stats()`'s output.
+    #[derive(serde::Deserialize)]
+    struct AutomatonStatsForTest {
+        memory_usage: usize,
+        pattern_count: usize,
+        min_pattern_len: usize,
+        max_pattern_len: usize,
+        #[allow(dead_code)]
+        engine: String,
+    }
+
+    // Mirrors `MatchResult`'s wire shape so tests can deserialize what `push`
+    // actually sends across the WASM boundary.
+    #[derive(serde::Deserialize)]
+    struct MatchResultForTest {
+        #[allow(dead_code)]
+        pattern_index: usize,
+        start: usize,
+        end: usize,
+        #[allow(dead_code)]
+        start_utf16: usize,
+        #[allow(dead_code)]
+        end_utf16: usize,
+    }
+}